@@ -35,5 +35,105 @@ impl Camera {
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
+    pub view_position: glam::Vec4,
     pub view_proj: glam::Mat4,
+}
+
+pub struct CameraController {
+    pub speed: f32,
+    is_forward_pressed: bool,
+    is_backward_pressed: bool,
+    is_left_pressed: bool,
+    is_right_pressed: bool,
+    is_orbiting: bool,
+    last_cursor_position: Option<(f64, f64)>,
+    yaw_delta: f32,
+}
+
+impl CameraController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            is_forward_pressed: false,
+            is_backward_pressed: false,
+            is_left_pressed: false,
+            is_right_pressed: false,
+            is_orbiting: false,
+            last_cursor_position: None,
+            yaw_delta: 0.0,
+        }
+    }
+
+    pub fn process_keyboard(&mut self, key: winit::keyboard::KeyCode, pressed: bool) -> bool {
+        match key {
+            winit::keyboard::KeyCode::KeyW | winit::keyboard::KeyCode::ArrowUp => {
+                self.is_forward_pressed = pressed;
+                true
+            }
+            winit::keyboard::KeyCode::KeyS | winit::keyboard::KeyCode::ArrowDown => {
+                self.is_backward_pressed = pressed;
+                true
+            }
+            winit::keyboard::KeyCode::KeyA | winit::keyboard::KeyCode::ArrowLeft => {
+                self.is_left_pressed = pressed;
+                true
+            }
+            winit::keyboard::KeyCode::KeyD | winit::keyboard::KeyCode::ArrowRight => {
+                self.is_right_pressed = pressed;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn process_mouse_button(&mut self, pressed: bool) {
+        self.is_orbiting = pressed;
+        if !pressed {
+            self.last_cursor_position = None;
+        }
+    }
+
+    pub fn process_cursor_moved(&mut self, position: (f64, f64)) {
+        if !self.is_orbiting {
+            self.last_cursor_position = Some(position);
+            return;
+        }
+
+        if let Some((last_x, _)) = self.last_cursor_position {
+            self.yaw_delta += (position.0 - last_x) as f32;
+        }
+        self.last_cursor_position = Some(position);
+    }
+
+    pub fn update_camera(&mut self, camera: &mut Camera) {
+        let forward = camera.center - camera.eye;
+        let forward_norm = forward.normalize();
+        let forward_mag = forward.length();
+
+        if self.is_forward_pressed && forward_mag > self.speed {
+            camera.eye += forward_norm * self.speed;
+        }
+        if self.is_backward_pressed {
+            camera.eye -= forward_norm * self.speed;
+        }
+
+        let right = forward_norm.cross(camera.up);
+
+        let forward = camera.center - camera.eye;
+        let forward_mag = forward.length();
+
+        if self.is_right_pressed {
+            camera.eye = camera.center - (forward + right * self.speed).normalize() * forward_mag;
+        }
+        if self.is_left_pressed {
+            camera.eye = camera.center - (forward - right * self.speed).normalize() * forward_mag;
+        }
+
+        if self.yaw_delta != 0.0 {
+            let rotation = glam::Quat::from_axis_angle(camera.up, f32::to_radians(-self.yaw_delta) * 0.2);
+            let offset = camera.eye - camera.center;
+            camera.eye = camera.center + rotation * offset;
+            self.yaw_delta = 0.0;
+        }
+    }
 }
\ No newline at end of file
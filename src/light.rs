@@ -0,0 +1,8 @@
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    pub position: glam::Vec3,
+    pub _pad: f32,
+    pub color: glam::Vec3,
+    pub _pad2: f32,
+}
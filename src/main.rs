@@ -1,19 +1,27 @@
+mod camera;
+mod light;
+mod model;
 mod renderer;
 
 use std::sync::Arc;
-use winit::event::WindowEvent;
+use winit::event::{ElementState, MouseButton, WindowEvent};
 use winit::event_loop::{ActiveEventLoop};
 use winit::event_loop::EventLoop;
+use winit::keyboard::PhysicalKey;
 use winit::window::WindowId;
 use winit::window::Window;
 
+use camera::CameraController;
 use renderer::Renderer;
 
+const CAMERA_SPEED: f32 = 0.05;
+
 enum App {
     Init,
     Main {
         window: Arc<Window>,
-        renderer: Renderer
+        renderer: Renderer,
+        camera_controller: CameraController,
     },
 }
 
@@ -23,10 +31,12 @@ impl winit::application::ApplicationHandler for App {
 
         let window = Arc::new(event_loop.create_window(Window::default_attributes()).unwrap());
         let renderer = Renderer::initialize(&window);
+        let camera_controller = CameraController::new(CAMERA_SPEED);
 
         *self = App::Main {
             window,
             renderer,
+            camera_controller,
         }
     }
 
@@ -36,7 +46,7 @@ impl winit::application::ApplicationHandler for App {
                 event_loop.exit();
             },
             WindowEvent::Resized(size) => {
-                let App::Main { renderer, window } = self else { return };
+                let App::Main { renderer, window, .. } = self else { return };
                 if size.width != 0 && size.height != 0 {
                     renderer.resize_surface(size);
                     window.request_redraw();
@@ -47,12 +57,28 @@ impl winit::application::ApplicationHandler for App {
 
                 renderer.draw();
             },
+            WindowEvent::KeyboardInput { event: key_event, .. } => {
+                let App::Main { camera_controller, .. } = self else { return };
+                if let PhysicalKey::Code(key_code) = key_event.physical_key {
+                    camera_controller.process_keyboard(key_code, key_event.state == ElementState::Pressed);
+                }
+            },
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                let App::Main { camera_controller, .. } = self else { return };
+                camera_controller.process_mouse_button(state == ElementState::Pressed);
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                let App::Main { camera_controller, .. } = self else { return };
+                camera_controller.process_cursor_moved((position.x, position.y));
+            },
             _ => {}
         }
     }
-    
+
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        let App::Main { window, .. } = self else { return };
+        let App::Main { window, renderer, camera_controller } = self else { return };
+        renderer.update_camera(camera_controller);
+        renderer.update_light();
         window.request_redraw();
     }
 }
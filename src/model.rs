@@ -0,0 +1,77 @@
+use std::io::{BufReader, Cursor};
+use glam::{Vec2, Vec3};
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ModelVertex {
+    pub position: Vec3,
+    pub tex_coords: Vec2,
+    pub normal: Vec3,
+}
+
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub num_elements: u32,
+}
+
+pub fn load_meshes(device: &wgpu::Device, obj_text: &str, mtl_text: &str) -> Vec<Mesh> {
+    let mut obj_reader = BufReader::new(Cursor::new(obj_text));
+
+    let (models, _materials) = tobj::load_obj_buf(
+        &mut obj_reader,
+        &tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        },
+        |_| tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mtl_text))),
+    ).unwrap();
+
+    models.into_iter().map(|model| {
+        let mesh = model.mesh;
+
+        let vertices: Vec<ModelVertex> = (0..mesh.positions.len() / 3).map(|i| {
+            let position = Vec3::new(
+                mesh.positions[i * 3],
+                mesh.positions[i * 3 + 1],
+                mesh.positions[i * 3 + 2],
+            );
+            let tex_coords = if mesh.texcoords.is_empty() {
+                Vec2::ZERO
+            } else {
+                Vec2::new(mesh.texcoords[i * 2], 1.0 - mesh.texcoords[i * 2 + 1])
+            };
+            let normal = if mesh.normals.is_empty() {
+                Vec3::ZERO
+            } else {
+                Vec3::new(
+                    mesh.normals[i * 3],
+                    mesh.normals[i * 3 + 1],
+                    mesh.normals[i * 3 + 2],
+                )
+            };
+
+            ModelVertex { position, tex_coords, normal }
+        }).collect();
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Mesh {
+            vertex_buffer,
+            index_buffer,
+            num_elements: mesh.indices.len() as u32,
+        }
+    }).collect()
+}
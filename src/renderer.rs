@@ -1,33 +1,35 @@
 use std::sync::Arc;
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
-use glam::Vec3;
+use glam::{Mat4, Quat, Vec3};
 use image::GenericImageView;
 use wgpu::util::DeviceExt;
 
 use crate::camera::*;
+use crate::light::LightUniform;
+use crate::model::{self, Mesh, ModelVertex};
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct VertexPositionColor {
-    position: Vec3,
-    color: Vec3
-}
+const NUM_INSTANCES_PER_ROW: u32 = 10;
+const SPACE_BETWEEN: f32 = 3.0;
 
-const VERTICES: &[VertexPositionColor] = &[
-    VertexPositionColor { position: Vec3::new(-0.0868241, 0.49240386, 0.0), color: Vec3::new(0.5, 0.0, 0.5) }, // A
-    VertexPositionColor { position: Vec3::new(-0.49513406, 0.06958647, 0.0), color: Vec3::new(0.5, 0.0, 0.5) }, // B
-    VertexPositionColor { position: Vec3::new(-0.21918549, -0.44939706, 0.0), color: Vec3::new(0.5, 0.0, 0.5) }, // C
-    VertexPositionColor { position: Vec3::new(0.35966998, -0.3473291, 0.0), color: Vec3::new(0.5, 0.0, 0.5) }, // D
-    VertexPositionColor { position: Vec3::new(0.44147372, 0.2347359, 0.0), color: Vec3::new(0.5, 0.0, 0.5) }, // E
-];
+pub struct Instance {
+    pub position: Vec3,
+    pub rotation: Quat,
+}
 
-const INDICES: &[u16] = &[
-    0, 1, 4,
-    1, 2, 4,
-    2, 3, 4,
-];
+impl Instance {
+    fn to_raw(&self) -> InstanceRaw {
+        InstanceRaw {
+            model: Mat4::from_rotation_translation(self.rotation, self.position),
+        }
+    }
+}
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanceRaw {
+    model: Mat4,
+}
 
 pub struct Renderer {
     pub instance: wgpu::Instance,
@@ -41,23 +43,58 @@ pub struct Renderer {
     pub shader_module: wgpu::ShaderModule,
     pub pipeline_layout: wgpu::PipelineLayout,
     pub render_pipeline: wgpu::RenderPipeline,
-    pub vertex_buffer: wgpu::Buffer,
-    pub index_buffer: wgpu::Buffer,
+    pub meshes: Vec<Mesh>,
+
+    pub instances: Vec<Instance>,
+    pub instance_buffer: wgpu::Buffer,
 
     pub camera: Camera,
     pub camera_uniform_buffer: wgpu::Buffer,
     pub camera_bind_group: wgpu::BindGroup,
+
+    pub diffuse_texture: wgpu::Texture,
+    pub diffuse_bind_group: wgpu::BindGroup,
+
+    pub depth_texture: wgpu::Texture,
+    pub depth_view: wgpu::TextureView,
+
+    pub light_position: Vec3,
+    pub light_color: Vec3,
+    pub light_uniform_buffer: wgpu::Buffer,
+    pub light_bind_group: wgpu::BindGroup,
+}
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+fn create_depth_texture(device: &wgpu::Device, surface_config: &wgpu::SurfaceConfiguration) -> (wgpu::Texture, wgpu::TextureView) {
+    let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: surface_config.width,
+            height: surface_config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    (depth_texture, depth_view)
 }
 
 impl Renderer {
     pub fn initialize(window: &Arc<Window>) -> Self {
         let window_size = window.inner_size();
-        
+
         let diffuse_bytes = include_bytes!("../happy-tree.png");
         let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
         let diffuse_rgba = diffuse_image.to_rgba8();
         let image_dimensions = diffuse_image.dimensions();
-        
+
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::PRIMARY,
             flags: wgpu::InstanceFlags::debugging(),
@@ -99,7 +136,7 @@ impl Renderer {
             label: None,
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into())
         });
-        
+
         let diffuse_texture = device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size: wgpu::Extent3d {
@@ -114,13 +151,97 @@ impl Renderer {
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
-        
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &diffuse_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &diffuse_rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * image_dimensions.0),
+                rows_per_image: Some(image_dimensions.1),
+            },
+            wgpu::Extent3d {
+                width: image_dimensions.0,
+                height: image_dimensions.1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let diffuse_texture_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let diffuse_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
         let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+        });
+
+        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let diffuse_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&diffuse_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&diffuse_sampler),
+                },
+            ],
+        });
+
+        let light_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -133,10 +254,33 @@ impl Renderer {
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&camera_bind_group_layout],
+            bind_group_layouts: &[&camera_bind_group_layout, &texture_bind_group_layout, &light_bind_group_layout],
             push_constant_ranges: &[],
         });
 
+        let instance_attributes: [wgpu::VertexAttribute; 4] = [
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 5,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                shader_location: 6,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                shader_location: 7,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                shader_location: 8,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ];
+
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: None,
             layout: Some(&pipeline_layout),
@@ -144,11 +288,18 @@ impl Renderer {
                 module: &shader_module,
                 entry_point: "vs_main",
                 compilation_options: Default::default(),
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<VertexPositionColor>() as wgpu::BufferAddress,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
-                }],
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<ModelVertex>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32x3],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &instance_attributes,
+                    },
+                ],
             },
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleList,
@@ -159,7 +310,13 @@ impl Renderer {
                 polygon_mode: wgpu::PolygonMode::Fill,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
@@ -178,19 +335,34 @@ impl Renderer {
             multiview: None,
             cache: None,
         });
-        
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+
+        let obj_text = include_str!("../cube.obj");
+        let mtl_text = include_str!("../cube.mtl");
+        let meshes = model::load_meshes(&device, obj_text, mtl_text);
+
+        let instances: Vec<Instance> = (0..NUM_INSTANCES_PER_ROW).flat_map(|z| {
+            (0..NUM_INSTANCES_PER_ROW).map(move |x| {
+                let x = SPACE_BETWEEN * (x as f32 - NUM_INSTANCES_PER_ROW as f32 / 2.0);
+                let z = SPACE_BETWEEN * (z as f32 - NUM_INSTANCES_PER_ROW as f32 / 2.0);
+
+                let position = Vec3::new(x, 0.0, z);
+                let rotation = if position == Vec3::ZERO {
+                    Quat::from_axis_angle(Vec3::Z, 0.0)
+                } else {
+                    Quat::from_axis_angle(position.normalize(), f32::to_radians(45.0))
+                };
+
+                Instance { position, rotation }
+            })
+        }).collect();
+
+        let instance_data: Vec<InstanceRaw> = instances.iter().map(Instance::to_raw).collect();
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
-            contents: bytemuck::cast_slice(VERTICES),
+            contents: bytemuck::cast_slice(&instance_data),
             usage: wgpu::BufferUsages::VERTEX,
         });
-        
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-        
+
         let camera = Camera {
             eye: Vec3::new(0.0, 1.0, 3.0),
             center: Vec3::new(0.0, 0.0, 0.0),
@@ -200,17 +372,18 @@ impl Renderer {
             znear: 0.1,
             zfar: 100.0,
         };
-        
+
         let camera_uniform = CameraUniform {
+            view_position: camera.eye.extend(1.0),
             view_proj: camera.build_view_projection_matrix()
         };
-        
+
         let camera_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
             contents: bytemuck::cast_slice(&[camera_uniform]),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
-        
+
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: None,
             layout: &camera_bind_group_layout,
@@ -219,7 +392,33 @@ impl Renderer {
                 resource: camera_uniform_buffer.as_entire_binding()
             }],
         });
-        
+
+        let (depth_texture, depth_view) = create_depth_texture(&device, &surface_config);
+
+        let light_position = Vec3::new(2.0, 2.0, 2.0);
+        let light_color = Vec3::new(1.0, 1.0, 1.0);
+        let light_uniform = LightUniform {
+            position: light_position,
+            _pad: 0.0,
+            color: light_color,
+            _pad2: 0.0,
+        };
+
+        let light_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[light_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &light_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: light_uniform_buffer.as_entire_binding()
+            }],
+        });
+
         Renderer {
             instance,
             surface,
@@ -231,23 +430,56 @@ impl Renderer {
             shader_module,
             pipeline_layout,
             render_pipeline,
-            vertex_buffer,
-            index_buffer,
+            meshes,
+            instances,
+            instance_buffer,
             camera,
             camera_uniform_buffer,
             camera_bind_group,
+            diffuse_texture,
+            diffuse_bind_group,
+            depth_texture,
+            depth_view,
+            light_position,
+            light_color,
+            light_uniform_buffer,
+            light_bind_group,
         }
     }
-    
+
+    pub fn update_light(&mut self) {
+        let light_uniform = LightUniform {
+            position: self.light_position,
+            _pad: 0.0,
+            color: self.light_color,
+            _pad2: 0.0,
+        };
+        self.queue.write_buffer(&self.light_uniform_buffer, 0, bytemuck::cast_slice(&[light_uniform]));
+    }
+
+    pub fn update_camera(&mut self, camera_controller: &mut CameraController) {
+        camera_controller.update_camera(&mut self.camera);
+
+        let camera_uniform = CameraUniform {
+            view_position: self.camera.eye.extend(1.0),
+            view_proj: self.camera.build_view_projection_matrix()
+        };
+        self.queue.write_buffer(&self.camera_uniform_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
+    }
+
     pub fn resize_surface(&mut self, size: PhysicalSize<u32>) {
         self.surface_config.width = size.width;
         self.surface_config.height = size.height;
         self.should_reconfigure_surface = true;
+
+        let (depth_texture, depth_view) = create_depth_texture(&self.device, &self.surface_config);
+        self.depth_texture = depth_texture;
+        self.depth_view = depth_view;
     }
-    
+
     pub fn draw(&self) {
         let renderer = &self;
-        
+
         if renderer.should_reconfigure_surface {
             renderer.surface.configure(&renderer.device, &renderer.surface_config);
         }
@@ -274,19 +506,32 @@ impl Renderer {
                                 store: wgpu::StoreOp::Store,
                             },
                         })],
-                        depth_stencil_attachment: None,
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: &renderer.depth_view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: wgpu::StoreOp::Store,
+                            }),
+                            stencil_ops: None,
+                        }),
                         timestamp_writes: None,
                         occlusion_query_set: None,
                     });
 
                     render_pass.set_pipeline(&renderer.render_pipeline);
                     render_pass.set_bind_group(0, &renderer.camera_bind_group, &[]);
-                    render_pass.set_vertex_buffer(0, renderer.vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(
-                        renderer.index_buffer.slice(..),
-                        wgpu::IndexFormat::Uint16
-                    );
-                    render_pass.draw_indexed(0..(INDICES.len() as u32), 0, 0..1);
+                    render_pass.set_bind_group(1, &renderer.diffuse_bind_group, &[]);
+                    render_pass.set_bind_group(2, &renderer.light_bind_group, &[]);
+                    render_pass.set_vertex_buffer(1, renderer.instance_buffer.slice(..));
+
+                    for mesh in &renderer.meshes {
+                        render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                        render_pass.set_index_buffer(
+                            mesh.index_buffer.slice(..),
+                            wgpu::IndexFormat::Uint32
+                        );
+                        render_pass.draw_indexed(0..mesh.num_elements, 0, 0..(renderer.instances.len() as u32));
+                    }
                 }
                 renderer.queue.submit(std::iter::once(commands.finish()));
                 frame.present();
@@ -296,4 +541,4 @@ impl Renderer {
             }
         }
     }
-}
\ No newline at end of file
+}